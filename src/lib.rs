@@ -1,3 +1,8 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 
 /// A bucketing algorithm for histograms.
@@ -10,6 +15,73 @@ pub trait Bucketing {
 
     /// The computed bucket ranges for this bucketing algorithm.
     fn ranges(&self) -> &[u64];
+
+    /// Whether [`ranges`](Bucketing::ranges) returns a usable, precomputed set of buckets.
+    ///
+    /// Bucketing algorithms that compute buckets purely on-the-fly, like [`Functional`],
+    /// don't have a fixed set of ranges and should override this to return `false`.
+    fn has_ranges(&self) -> bool {
+        true
+    }
+}
+
+/// RAII guard that forces the FPU into a defined rounding/precision mode for the
+/// duration of floating-point bucket math.
+///
+/// `Functional` and `PrecomputedExponential` derive bucket boundaries from `log`/`powf`.
+/// On 32-bit x86, the x87 FPU evaluates these in 80-bit extended precision unless told
+/// otherwise, which can round differently than a strict IEEE-754 `f64` and produce
+/// different bucket minimums on different CPUs, corrupting aggregation of histograms
+/// collected across a fleet. Holding a `FloatingPointContext` for the duration of that math
+/// sets the x87 control word to 64-bit double precision and restores the previous value on
+/// drop, so bucket minimums are reproducible given identical inputs regardless of target.
+/// On every other architecture (including x86_64, which uses SSE2 for `f64` math) this is a
+/// no-op.
+pub struct FloatingPointContext {
+    #[cfg(target_arch = "x86")]
+    previous_control_word: u16,
+}
+
+impl FloatingPointContext {
+    /// Enter the defined-precision FPU context for the lifetime of the returned guard.
+    #[cfg(target_arch = "x86")]
+    pub fn new() -> FloatingPointContext {
+        // Precision control lives in bits 8-9 of the x87 control word; `0b10` selects
+        // 64-bit double precision (rather than the default 80-bit extended precision).
+        const PRECISION_MASK: u16 = 0x0300;
+        const DOUBLE_PRECISION: u16 = 0x0200;
+
+        unsafe {
+            let mut previous_control_word: u16 = 0;
+            std::arch::asm!("fnstcw [{0}]", in(reg) &mut previous_control_word);
+
+            let double_precision_cw = (previous_control_word & !PRECISION_MASK) | DOUBLE_PRECISION;
+            std::arch::asm!("fldcw [{0}]", in(reg) &double_precision_cw);
+
+            FloatingPointContext { previous_control_word }
+        }
+    }
+
+    /// Enter the defined-precision FPU context for the lifetime of the returned guard.
+    #[cfg(not(target_arch = "x86"))]
+    pub fn new() -> FloatingPointContext {
+        FloatingPointContext {}
+    }
+}
+
+impl Default for FloatingPointContext {
+    fn default() -> Self {
+        FloatingPointContext::new()
+    }
+}
+
+#[cfg(target_arch = "x86")]
+impl Drop for FloatingPointContext {
+    fn drop(&mut self) {
+        unsafe {
+            std::arch::asm!("fldcw [{0}]", in(reg) &self.previous_control_word);
+        }
+    }
 }
 
 /// A functional bucketing algorithm.
@@ -38,11 +110,13 @@ impl Functional {
     /// mathematical concept, even though the internal representation is stored and
     /// sent using the minimum value in each bucket.
     fn sample_to_bucket_index(&self, sample: u64) -> u64 {
+        let _fpu = FloatingPointContext::new();
         ((sample + 1) as f64).log(self.exponent) as u64
     }
 
     /// Determines the minimum value of a bucket, given a bucket index.
     fn bucket_index_to_bucket_minimum(&self, index: u64) -> u64 {
+        let _fpu = FloatingPointContext::new();
         self.exponent.powf(index as f64) as u64
     }
 }
@@ -60,6 +134,653 @@ impl Bucketing for Functional {
     fn ranges(&self) -> &[u64] {
         unimplemented!("Bucket ranges for functional bucketing are not precomputed")
     }
+
+    fn has_ranges(&self) -> bool {
+        false
+    }
+}
+
+/// A precomputed exponential bucketing algorithm.
+///
+/// Bucket ranges are computed once, ahead of time, from a `min`, a `max` and a
+/// `bucket_count`, and then reused for every sample. Unlike [`Functional`], this
+/// produces a bounded set of buckets spanning `[min, max]`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrecomputedExponential {
+    min: u64,
+    max: u64,
+    bucket_count: usize,
+
+    #[serde(skip)]
+    ranges: OnceCell<Vec<u64>>,
+}
+
+impl PrecomputedExponential {
+    /// Instantiate a new precomputed exponential bucketing.
+    pub fn new(min: u64, max: u64, bucket_count: usize) -> PrecomputedExponential {
+        PrecomputedExponential {
+            min,
+            max,
+            bucket_count,
+            ranges: OnceCell::new(),
+        }
+    }
+
+    /// Compute the bucket ranges for this bucketing algorithm.
+    ///
+    /// This is only done once and then cached.
+    fn compute_ranges(&self) -> Vec<u64> {
+        let _fpu = FloatingPointContext::new();
+
+        let mut ranges = Vec::with_capacity(self.bucket_count);
+
+        // The first bucket is always an underflow bucket.
+        ranges.push(0);
+
+        let max = self.max as f64;
+        let mut current = std::cmp::max(self.min, 1);
+        ranges.push(current);
+
+        for i in 2..self.bucket_count {
+            let log_current = (current as f64).ln();
+            let log_ratio = (max.ln() - log_current) / (self.bucket_count - i) as f64;
+            let mut next = (log_current + log_ratio).exp().round() as u64;
+            if next <= current {
+                // Bucket limits must be strictly increasing.
+                next = current + 1;
+            }
+
+            current = next;
+            ranges.push(current);
+        }
+
+        ranges
+    }
+}
+
+impl Bucketing for PrecomputedExponential {
+    fn sample_to_bucket_minimum(&self, sample: u64) -> u64 {
+        let ranges = self.ranges();
+
+        match ranges.binary_search(&sample) {
+            Ok(idx) => ranges[idx],
+            Err(0) => ranges[0],
+            Err(idx) => ranges[idx - 1],
+        }
+    }
+
+    fn ranges(&self) -> &[u64] {
+        self.ranges.get_or_init(|| self.compute_ranges())
+    }
+}
+
+/// A precomputed linear bucketing algorithm.
+///
+/// Bucket ranges are computed once, ahead of time, from a `min`, a `max` and a
+/// `bucket_count`, and spaced evenly between `min` and `max`. This complements
+/// [`PrecomputedExponential`] for data that isn't exponentially distributed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PrecomputedLinear {
+    min: u64,
+    max: u64,
+    bucket_count: usize,
+
+    #[serde(skip)]
+    ranges: OnceCell<Vec<u64>>,
+}
+
+impl PrecomputedLinear {
+    /// Instantiate a new precomputed linear bucketing.
+    pub fn new(min: u64, max: u64, bucket_count: usize) -> PrecomputedLinear {
+        PrecomputedLinear {
+            min,
+            max,
+            bucket_count,
+            ranges: OnceCell::new(),
+        }
+    }
+
+    /// Compute the bucket ranges for this bucketing algorithm.
+    ///
+    /// This is only done once and then cached.
+    fn compute_ranges(&self) -> Vec<u64> {
+        let mut ranges = Vec::with_capacity(self.bucket_count);
+
+        // The first bucket is always an underflow bucket.
+        ranges.push(0);
+
+        let range = (self.max - self.min) as u128;
+        let divisor = (self.bucket_count - 1) as u128;
+
+        for i in 1..self.bucket_count {
+            // Integer math, not f64: f64 only has 53 bits of mantissa, which silently
+            // loses precision for `u64` bounds above 2^53.
+            let bucket_min = (range * i as u128 / divisor) as u64 + self.min;
+            // The range may be narrower than the bucket count, in which case
+            // successive buckets can collide; only keep strictly increasing bounds.
+            if ranges.last() != Some(&bucket_min) {
+                ranges.push(bucket_min);
+            }
+        }
+
+        ranges
+    }
+}
+
+impl Bucketing for PrecomputedLinear {
+    fn sample_to_bucket_minimum(&self, sample: u64) -> u64 {
+        let ranges = self.ranges();
+
+        match ranges.binary_search(&sample) {
+            Ok(idx) => ranges[idx],
+            Err(0) => ranges[0],
+            Err(idx) => ranges[idx - 1],
+        }
+    }
+
+    fn ranges(&self) -> &[u64] {
+        self.ranges.get_or_init(|| self.compute_ranges())
+    }
+}
+
+/// A histogram that accumulates samples into buckets determined by a [`Bucketing`]
+/// algorithm.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Histogram<B> {
+    values: HashMap<u64, u64>,
+    count: u64,
+    sum: u64,
+    bucketing: B,
+}
+
+impl<B: Bucketing> Histogram<B> {
+    /// Create a histogram using the given bucketing algorithm.
+    pub fn new(bucketing: B) -> Histogram<B> {
+        Histogram {
+            values: HashMap::new(),
+            count: 0,
+            sum: 0,
+            bucketing,
+        }
+    }
+
+    /// Record a sample in the histogram.
+    pub fn accumulate(&mut self, sample: u64) {
+        let bucket_min = self.bucketing.sample_to_bucket_minimum(sample);
+        *self.values.entry(bucket_min).or_insert(0) += 1;
+        self.count += 1;
+        self.sum += sample;
+    }
+
+    /// The number of samples recorded in this histogram.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The sum of all samples recorded in this histogram.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Whether any sample has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// A snapshot of the bucket counts, keyed by bucket minimum.
+    ///
+    /// If the underlying bucketing algorithm has precomputed [`Bucketing::ranges`], the
+    /// result is dense: every bucket in those ranges is present, with a count of `0` for
+    /// buckets that never received a sample.
+    pub fn snapshot_values(&self) -> BTreeMap<u64, u64> {
+        let mut snapshot: BTreeMap<u64, u64> = self.values.iter().map(|(&k, &v)| (k, v)).collect();
+
+        if self.bucketing.has_ranges() {
+            for &bucket in self.bucketing.ranges() {
+                snapshot.entry(bucket).or_insert(0);
+            }
+        }
+
+        snapshot
+    }
+
+    /// Estimate the value at quantile `q` (in `[0, 1]`) from the accumulated buckets.
+    ///
+    /// Since only bucket counts are kept, not raw samples, the result is an approximation:
+    /// the threshold rank `r = q * count` is located among the ascending bucket minimums, and
+    /// linearly interpolated between the crossing bucket's lower bound and the lower bound of
+    /// the next bucket in [`snapshot_values`](Histogram::snapshot_values). For bucketing
+    /// without precomputed ranges, such as [`Functional`], that is the next bucket that
+    /// received samples, not the adjacent bucket. Returns `None` if no samples have been
+    /// recorded.
+    pub fn percentile(&self, q: f64) -> Option<u64> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let snapshot = self.snapshot_values();
+
+        if q <= 0.0 {
+            return snapshot
+                .iter()
+                .find(|&(_, &count)| count > 0)
+                .map(|(&bucket, _)| bucket);
+        }
+
+        let r = q * self.count as f64;
+        let mut cum_before = 0u64;
+        let mut iter = snapshot.iter().peekable();
+
+        while let Some((&lower, &bucket_count)) = iter.next() {
+            let cum_after = cum_before + bucket_count;
+
+            if cum_after as f64 >= r {
+                return match iter.peek() {
+                    Some(&(&next_lower, _)) => {
+                        let fraction = (r - cum_before as f64) / bucket_count as f64;
+                        Some(lower + ((next_lower - lower) as f64 * fraction) as u64)
+                    }
+                    // The final, open-ended bucket has no upper neighbor to interpolate towards.
+                    None => Some(lower),
+                };
+            }
+
+            cum_before = cum_after;
+        }
+
+        snapshot.keys().last().copied()
+    }
+
+    /// Render a terminal-friendly report: sample count, min, max, mean, variance and
+    /// standard deviation, followed by one line per populated bucket with its range and a
+    /// bar scaled to `max_bar_width` characters at the largest bucket count.
+    ///
+    /// A bucket's range runs up to the next bucket in
+    /// [`snapshot_values`](Histogram::snapshot_values); for bucketing without precomputed
+    /// ranges, such as [`Functional`], that is the next bucket that received samples.
+    pub fn render(&self, max_bar_width: usize) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+
+        if self.is_empty() {
+            let _ = writeln!(out, "Histogram: no samples recorded");
+            return out;
+        }
+
+        let entries: Vec<(u64, u64)> = self.snapshot_values().into_iter().collect();
+        let mean = self.sum as f64 / self.count as f64;
+        let variance = entries
+            .iter()
+            .map(|&(bucket, count)| {
+                let diff = bucket as f64 - mean;
+                diff * diff * count as f64
+            })
+            .sum::<f64>()
+            / self.count as f64;
+
+        let min = entries.iter().find(|&&(_, count)| count > 0).map(|&(b, _)| b);
+        let max = entries.iter().rev().find(|&&(_, count)| count > 0).map(|&(b, _)| b);
+        let largest_count = entries.iter().map(|&(_, count)| count).max().unwrap_or(0).max(1);
+
+        let _ = writeln!(out, "count: {}", self.count);
+        let _ = writeln!(out, "min: {}", min.unwrap_or(0));
+        let _ = writeln!(out, "max: {}", max.unwrap_or(0));
+        let _ = writeln!(out, "mean: {:.2}", mean);
+        let _ = writeln!(out, "variance: {:.2}", variance);
+        let _ = writeln!(out, "std dev: {:.2}", variance.sqrt());
+        let _ = writeln!(out);
+
+        for (i, &(lower, count)) in entries.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            let range = match entries.get(i + 1) {
+                Some(&(next, _)) => format!("[{}, {})", lower, next),
+                None => format!("[{}, +)", lower),
+            };
+            let bar_width = (count as f64 / largest_count as f64 * max_bar_width as f64).round() as usize;
+            let bar = "#".repeat(bar_width);
+
+            let _ = writeln!(out, "{:>20} | {:<width$} {}", range, bar, count, width = max_bar_width);
+        }
+
+        out
+    }
+
+    /// Merge `other`'s recorded samples into this histogram.
+    ///
+    /// Both histograms must use the same bucketing algorithm so that bucket minimums line
+    /// up; this lets partial aggregations computed in parallel be combined cheaply.
+    pub fn merge(&mut self, other: &Histogram<B>) {
+        for (&bucket_min, &count) in &other.values {
+            *self.values.entry(bucket_min).or_insert(0) += count;
+        }
+
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+
+    /// Bucket the recorded samples into intervals for analytics-style aggregation.
+    ///
+    /// See [`AggregationConfig`] for the interval, offset, sparse/dense output mode,
+    /// `min_doc_count` filter and `extended_bounds` it accepts.
+    pub fn aggregate(&self, config: &AggregationConfig) -> Vec<AggregationBucket> {
+        let mut buckets: BTreeMap<u64, u64> = BTreeMap::new();
+        for (&bucket_min, &count) in &self.values {
+            let key = Self::interval_key(bucket_min, config.interval, config.offset);
+            *buckets.entry(key).or_insert(0) += count;
+        }
+
+        buckets.retain(|_, &mut count| count >= config.min_doc_count);
+
+        match config.mode {
+            AggregationMode::Sparse => buckets
+                .into_iter()
+                .map(|(key, count)| AggregationBucket { key, count })
+                .collect(),
+            AggregationMode::Dense => {
+                let observed_range = buckets
+                    .keys()
+                    .next()
+                    .copied()
+                    .zip(buckets.keys().next_back().copied());
+
+                let range = match (observed_range, config.extended_bounds) {
+                    (Some((min_key, max_key)), Some(bounds)) => {
+                        let bounds_min = Self::interval_key(bounds.min, config.interval, config.offset);
+                        let bounds_max = Self::interval_key(bounds.max, config.interval, config.offset);
+                        Some((min_key.min(bounds_min), max_key.max(bounds_max)))
+                    }
+                    (Some(observed), None) => Some(observed),
+                    (None, Some(bounds)) => Some((
+                        Self::interval_key(bounds.min, config.interval, config.offset),
+                        Self::interval_key(bounds.max, config.interval, config.offset),
+                    )),
+                    (None, None) => None,
+                };
+
+                let (min_key, max_key) = match range {
+                    Some(range) => range,
+                    None => return Vec::new(),
+                };
+
+                let mut result = Vec::new();
+                let mut key = min_key;
+                while key <= max_key {
+                    result.push(AggregationBucket {
+                        key,
+                        count: buckets.get(&key).copied().unwrap_or(0),
+                    });
+                    key += config.interval;
+                }
+                result
+            }
+        }
+    }
+
+    /// Map a recorded bucket minimum to the interval-aligned aggregation key it falls into.
+    fn interval_key(value: u64, interval: u64, offset: u64) -> u64 {
+        let shifted = value.saturating_sub(offset);
+        (shifted / interval) * interval + offset
+    }
+}
+
+impl<B: Bucketing> fmt::Display for Histogram<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(40))
+    }
+}
+
+/// How [`Histogram::aggregate`] should report intervals that received no samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregationMode {
+    /// Emit only intervals that have at least `min_doc_count` samples.
+    Sparse,
+    /// Emit every interval between the observed (or extended) min and max, zero-filling
+    /// intervals that received no samples or were dropped by `min_doc_count`.
+    Dense,
+}
+
+/// Bounds that force a [`Histogram::aggregate`] call to span at least `[min, max]`, even
+/// when no samples fall there. Only affects [`AggregationMode::Dense`] output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtendedBounds {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Error returned when an [`AggregationConfig`] is constructed with an invalid interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidAggregationConfig;
+
+impl fmt::Display for InvalidAggregationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid aggregation configuration: interval must be non-zero")
+    }
+}
+
+impl std::error::Error for InvalidAggregationConfig {}
+
+/// Configuration for [`Histogram::aggregate`]: buckets samples into intervals of size
+/// `interval` starting at `offset`, optionally filtering sparse buckets below
+/// `min_doc_count` and extending the emitted range with `extended_bounds`.
+#[derive(Debug, Clone)]
+pub struct AggregationConfig {
+    interval: u64,
+    offset: u64,
+    mode: AggregationMode,
+    min_doc_count: u64,
+    extended_bounds: Option<ExtendedBounds>,
+}
+
+impl AggregationConfig {
+    /// Create a config that aggregates into sparse intervals of the given size.
+    ///
+    /// Rejects `interval == 0`, which would otherwise divide by zero (or loop forever in
+    /// dense mode) once passed to [`Histogram::aggregate`].
+    pub fn new(interval: u64) -> Result<AggregationConfig, InvalidAggregationConfig> {
+        if interval == 0 {
+            return Err(InvalidAggregationConfig);
+        }
+
+        Ok(AggregationConfig {
+            interval,
+            offset: 0,
+            mode: AggregationMode::Sparse,
+            min_doc_count: 0,
+            extended_bounds: None,
+        })
+    }
+
+    /// Shift interval boundaries to start at `offset` instead of `0`.
+    pub fn offset(mut self, offset: u64) -> AggregationConfig {
+        self.offset = offset;
+        self
+    }
+
+    /// Choose between sparse and dense output.
+    pub fn mode(mut self, mode: AggregationMode) -> AggregationConfig {
+        self.mode = mode;
+        self
+    }
+
+    /// Drop intervals with fewer than `min_doc_count` samples.
+    pub fn min_doc_count(mut self, min_doc_count: u64) -> AggregationConfig {
+        self.min_doc_count = min_doc_count;
+        self
+    }
+
+    /// Force the emitted range to span at least `[min, max]`.
+    pub fn extended_bounds(mut self, min: u64, max: u64) -> AggregationConfig {
+        self.extended_bounds = Some(ExtendedBounds { min, max });
+        self
+    }
+}
+
+/// A single interval produced by [`Histogram::aggregate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AggregationBucket {
+    pub key: u64,
+    pub count: u64,
+}
+
+impl Histogram<Functional> {
+    /// Create a histogram using functional bucketing.
+    pub fn functional(log_base: f64, buckets_per_magnitude: f64) -> Histogram<Functional> {
+        Histogram::new(Functional::new(log_base, buckets_per_magnitude))
+    }
+}
+
+impl Histogram<PrecomputedExponential> {
+    /// Create a histogram using precomputed exponential bucketing.
+    pub fn exponential(min: u64, max: u64, bucket_count: usize) -> Histogram<PrecomputedExponential> {
+        Histogram::new(PrecomputedExponential::new(min, max, bucket_count))
+    }
+}
+
+impl Histogram<PrecomputedLinear> {
+    /// Create a histogram using precomputed linear bucketing.
+    pub fn linear(min: u64, max: u64, bucket_count: usize) -> Histogram<PrecomputedLinear> {
+        Histogram::new(PrecomputedLinear::new(min, max, bucket_count))
+    }
+}
+
+/// Error returned when an [`AtomicHistogram`] is built from invalid `(m, r, n)` exponents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidBucketConfig {
+    m: u32,
+    r: u32,
+    n: u32,
+}
+
+impl fmt::Display for InvalidBucketConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid atomic histogram configuration: require 1 <= m <= r <= n < 64, got m={}, r={}, n={}",
+            self.m, self.r, self.n
+        )
+    }
+}
+
+impl std::error::Error for InvalidBucketConfig {}
+
+/// Builder for [`AtomicHistogram`], validating the `(m, r, n)` exponents before allocating
+/// the bucket storage.
+#[derive(Debug, Clone, Copy)]
+pub struct AtomicHistogramBuilder {
+    m: u32,
+    r: u32,
+    n: u32,
+}
+
+impl AtomicHistogramBuilder {
+    /// Start building an atomic histogram with the given `(m, r, n)` exponents.
+    pub fn new(m: u32, r: u32, n: u32) -> AtomicHistogramBuilder {
+        AtomicHistogramBuilder { m, r, n }
+    }
+
+    /// Validate the exponents and allocate the bucket storage.
+    ///
+    /// Requires `1 <= m <= r <= n < 64`: `m == 0` is rejected because the
+    /// exponential-region bucket index extracts `r - m` mantissa bits below the value's
+    /// highest set bit, and at the linear/exponential boundary only `r - 1` such bits
+    /// exist. `n >= 64` is rejected because computing `N = 2^n - 1` as `(1u64 << n) - 1`
+    /// would overflow the shift.
+    pub fn build(self) -> Result<AtomicHistogram, InvalidBucketConfig> {
+        if !(self.m >= 1 && self.m <= self.r && self.r <= self.n && self.n < 64) {
+            return Err(InvalidBucketConfig {
+                m: self.m,
+                r: self.r,
+                n: self.n,
+            });
+        }
+
+        let shift = self.r - self.m;
+        let linear_buckets = 1usize << shift;
+        let exponential_buckets = ((self.n - self.r + 1) as usize) << shift;
+        let bucket_count = linear_buckets + exponential_buckets;
+
+        let buckets = (0..bucket_count)
+            .map(|_| AtomicU32::new(0))
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+
+        Ok(AtomicHistogram {
+            m: self.m,
+            r: self.r,
+            n: self.n,
+            linear_buckets,
+            buckets,
+        })
+    }
+}
+
+/// A lock-free, allocation-free histogram suited to concurrent recording on hot paths.
+///
+/// Bucket indices are derived purely with integer bit operations rather than
+/// floating-point logarithms, so recording is deterministic and branch-cheap. Three
+/// exponents parameterize the layout: `m` sets the smallest, linear-region bucket width
+/// `M = 2^m`; `r` sets the size of the linear region `R = 2^r - 1`; and `n` sets the
+/// largest representable value `N = 2^n - 1`. Values below `R` fall into equal-width
+/// linear buckets; values at or above `R` fall into exponentially growing buckets derived
+/// from their highest set bit.
+#[derive(Debug)]
+pub struct AtomicHistogram {
+    m: u32,
+    r: u32,
+    n: u32,
+    linear_buckets: usize,
+    buckets: Box<[AtomicU32]>,
+}
+
+impl AtomicHistogram {
+    /// Start building an atomic histogram with the given `(m, r, n)` exponents.
+    pub fn builder(m: u32, r: u32, n: u32) -> AtomicHistogramBuilder {
+        AtomicHistogramBuilder::new(m, r, n)
+    }
+
+    /// The total number of buckets backing this histogram.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// The largest representable value `N = 2^n - 1` before recording saturates.
+    pub fn max_value(&self) -> u64 {
+        (1u64 << self.n) - 1
+    }
+
+    /// Record a single occurrence of `value`, saturating into the largest bucket if
+    /// `value` exceeds `N`.
+    pub fn accumulate(&self, value: u64) {
+        let index = self.bucket_index(value).min(self.buckets.len() - 1);
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Snapshot the current bucket counts.
+    pub fn snapshot(&self) -> Vec<u32> {
+        self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect()
+    }
+
+    /// Map `value` to its bucket index using only integer bit operations.
+    fn bucket_index(&self, value: u64) -> usize {
+        let linear_range = (1u64 << self.r) - 1;
+
+        if value < linear_range {
+            (value >> self.m) as usize
+        } else {
+            let shift = (self.r - self.m) as u64;
+            let h = 63 - value.leading_zeros() as u64;
+            // `h + 1 - r`, not `h - r + 1`: `value >= linear_range` guarantees `h >= r - 1`,
+            // but computing the subtraction in that order would underflow the unsigned `h - r`.
+            let offset =
+                ((h + 1 - self.r as u64) << shift) + ((value >> (h - shift)) & ((1u64 << shift) - 1));
+
+            self.linear_buckets + offset as usize
+        }
+    }
 }
 
 #[cfg(test)]
@@ -88,4 +809,242 @@ mod test {
             assert_eq!(output, f.bucket_index_to_bucket_minimum(input), "Input: {}, output: {}", input, output);
         }
     }
+
+    #[test]
+    fn precomputed_exponential_ranges_are_strictly_increasing() {
+        let b = PrecomputedExponential::new(1, 500_000, 50);
+        let ranges = b.ranges();
+
+        assert_eq!(ranges.len(), 50);
+        assert_eq!(ranges[0], 0);
+
+        for window in ranges.windows(2) {
+            assert!(window[0] < window[1], "{} should be < {}", window[0], window[1]);
+        }
+    }
+
+    #[test]
+    fn precomputed_exponential_sample_to_bucket_minimum() {
+        let b = PrecomputedExponential::new(1, 500_000, 50);
+        let ranges = b.ranges().to_vec();
+
+        for &bound in &ranges {
+            assert_eq!(b.sample_to_bucket_minimum(bound), bound);
+        }
+
+        // A sample that falls strictly between two bounds belongs to the lower one.
+        let (lower, upper) = ranges
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .find(|&(lower, upper)| upper - lower > 1)
+            .expect("at least one bucket should span more than one value");
+        assert_eq!(b.sample_to_bucket_minimum(lower + 1), lower);
+        assert!(lower + 1 < upper);
+    }
+
+    #[test]
+    fn precomputed_linear_ranges_dedup_narrow_spans() {
+        // Requesting more buckets than the value range can support must not produce
+        // duplicate (non-increasing) bounds.
+        let b = PrecomputedLinear::new(0, 5, 50);
+        let ranges = b.ranges();
+
+        for window in ranges.windows(2) {
+            assert!(window[0] < window[1]);
+        }
+        assert_eq!(*ranges.last().unwrap(), 5);
+    }
+
+    #[test]
+    fn precomputed_linear_sample_to_bucket_minimum() {
+        let b = PrecomputedLinear::new(0, 100, 11);
+        assert_eq!(b.sample_to_bucket_minimum(0), 0);
+        assert_eq!(b.sample_to_bucket_minimum(25), 20);
+        assert_eq!(b.sample_to_bucket_minimum(100), 100);
+    }
+
+    #[test]
+    fn precomputed_linear_ranges_keep_precision_above_2_pow_53() {
+        // f64 only has 53 bits of mantissa; this range must be computed in integer math to
+        // avoid silently rounding to the wrong bound.
+        let b = PrecomputedLinear::new(0, 1_000_000_000_000_000_000, 7);
+        assert_eq!(
+            b.ranges(),
+            &[
+                0,
+                166_666_666_666_666_666,
+                333_333_333_333_333_333,
+                500_000_000_000_000_000,
+                666_666_666_666_666_666,
+                833_333_333_333_333_333,
+                1_000_000_000_000_000_000,
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_accumulates_samples() {
+        let mut h = Histogram::linear(0, 100, 11);
+        assert!(h.is_empty());
+
+        h.accumulate(5);
+        h.accumulate(15);
+        h.accumulate(15);
+
+        assert!(!h.is_empty());
+        assert_eq!(h.count(), 3);
+        assert_eq!(h.sum(), 35);
+    }
+
+    #[test]
+    fn histogram_snapshot_values_is_dense_for_precomputed_bucketing() {
+        let mut h = Histogram::linear(0, 100, 11);
+        h.accumulate(15);
+
+        let snapshot = h.snapshot_values();
+        // Every bucket bound is present, not just the populated one.
+        assert_eq!(snapshot.len(), h.bucketing.ranges().len());
+        assert_eq!(snapshot[&10], 1);
+        assert_eq!(snapshot[&0], 0);
+    }
+
+    #[test]
+    fn histogram_snapshot_values_is_sparse_for_functional_bucketing() {
+        let mut h = Histogram::functional(2.0, 8.0);
+        h.accumulate(100);
+
+        let snapshot = h.snapshot_values();
+        assert_eq!(snapshot.len(), 1);
+    }
+
+    #[test]
+    fn percentile_of_empty_histogram_is_none() {
+        let h = Histogram::linear(0, 100, 11);
+        assert_eq!(h.percentile(0.5), None);
+    }
+
+    #[test]
+    fn percentile_interpolates_within_bucket() {
+        let mut h = Histogram::linear(0, 100, 11);
+        for sample in 0..100 {
+            h.accumulate(sample);
+        }
+
+        assert_eq!(h.percentile(0.0), Some(0));
+        let p50 = h.percentile(0.5).unwrap();
+        assert!((40..=60).contains(&p50), "p50 was {}", p50);
+    }
+
+    #[test]
+    fn render_includes_summary_and_bars() {
+        let mut h = Histogram::linear(0, 100, 11);
+        h.accumulate(10);
+        h.accumulate(20);
+
+        let report = h.render(20);
+        assert!(report.contains("count: 2"));
+        assert!(report.contains('#'));
+    }
+
+    #[test]
+    fn floating_point_context_can_be_entered_and_dropped() {
+        let _guard = FloatingPointContext::new();
+    }
+
+    #[test]
+    fn atomic_histogram_builder_rejects_invalid_exponents() {
+        assert!(AtomicHistogram::builder(5, 3, 8).build().is_err());
+        // m == 0 would underflow the mantissa-bit extraction in `bucket_index` right at the
+        // linear/exponential boundary, where only `r - 1` bits are available below the
+        // value's highest set bit.
+        assert!(AtomicHistogram::builder(0, 4, 8).build().is_err());
+        // n >= 64 would overflow the `1u64 << n` shift in `max_value`.
+        assert!(AtomicHistogram::builder(1, 1, 64).build().is_err());
+        assert!(AtomicHistogram::builder(2, 4, 8).build().is_ok());
+    }
+
+    #[test]
+    fn atomic_histogram_bucket_index_linear_and_exponential_regions() {
+        let h = AtomicHistogram::builder(2, 4, 8).build().unwrap();
+        assert_eq!(h.bucket_count(), 24);
+        assert_eq!(h.max_value(), 255);
+
+        h.accumulate(0);
+        h.accumulate(14);
+        h.accumulate(15);
+
+        let snapshot = h.snapshot();
+        assert_eq!(snapshot[0], 1); // 0 >> 2 == 0
+        assert_eq!(snapshot[3], 1); // 14 >> 2 == 3
+        // 15 is the smallest value routed to the exponential branch (the linear region
+        // covers 0..15), but it lands at global index 7, not at the exponential region's
+        // first index (4): its offset within the region is 3, not 0.
+        assert_eq!(snapshot[7], 1);
+
+        // Values above N saturate into the last bucket instead of panicking.
+        h.accumulate(u64::MAX);
+        assert_eq!(h.snapshot()[23], 1);
+    }
+
+    #[test]
+    fn aggregation_config_rejects_zero_interval() {
+        assert!(AggregationConfig::new(0).is_err());
+    }
+
+    #[test]
+    fn aggregate_sparse_buckets_by_interval() {
+        let mut h = Histogram::linear(0, 100, 11);
+        h.accumulate(12);
+        h.accumulate(17);
+        h.accumulate(55);
+
+        let buckets = h.aggregate(&AggregationConfig::new(10).unwrap());
+        let total: u64 = buckets.iter().map(|b| b.count).sum();
+        assert_eq!(total, 3);
+        assert!(buckets.iter().any(|b| b.key == 10 && b.count == 2));
+    }
+
+    #[test]
+    fn aggregate_dense_zero_fills_and_respects_extended_bounds() {
+        let mut h = Histogram::linear(0, 100, 11);
+        h.accumulate(10);
+        h.accumulate(30);
+
+        let buckets = h.aggregate(
+            &AggregationConfig::new(10)
+                .unwrap()
+                .mode(AggregationMode::Dense)
+                .extended_bounds(0, 50),
+        );
+
+        assert_eq!(buckets.first().unwrap().key, 0);
+        assert_eq!(buckets.last().unwrap().key, 50);
+        assert!(buckets.iter().any(|b| b.key == 20 && b.count == 0));
+    }
+
+    #[test]
+    fn aggregate_min_doc_count_filters_sparse_buckets() {
+        let mut h = Histogram::linear(0, 100, 11);
+        h.accumulate(10);
+        h.accumulate(30);
+        h.accumulate(30);
+
+        let buckets = h.aggregate(&AggregationConfig::new(10).unwrap().min_doc_count(2));
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].key, 30);
+    }
+
+    #[test]
+    fn merge_combines_two_histograms() {
+        let mut a = Histogram::linear(0, 100, 11);
+        let mut b = Histogram::linear(0, 100, 11);
+        a.accumulate(10);
+        b.accumulate(20);
+        b.accumulate(20);
+
+        a.merge(&b);
+
+        assert_eq!(a.count(), 3);
+        assert_eq!(a.sum(), 50);
+    }
 }